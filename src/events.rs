@@ -0,0 +1,186 @@
+//! ABI definitions for the events this indexer understands, and the shared
+//! decode helper they're all run through. `PoolCreated` is emitted by the
+//! factory; `Swap`/`Mint`/`Burn` are emitted by each individual pool once
+//! it has been discovered.
+
+use web3::ethabi::{Event, EventParam, Log as AbiLog, ParamType, RawLog};
+use web3::types::Log;
+
+/// `PoolCreated(address indexed token0, address indexed token1, uint24 indexed fee, int24 tickSpacing, address pool)`
+pub fn pool_created_event() -> Event {
+    Event {
+        name: "PoolCreated".to_string(),
+        inputs: vec![
+            EventParam {
+                name: "token0".to_string(),
+                kind: ParamType::Address,
+                indexed: true,
+            },
+            EventParam {
+                name: "token1".to_string(),
+                kind: ParamType::Address,
+                indexed: true,
+            },
+            EventParam {
+                name: "fee".to_string(),
+                kind: ParamType::Uint(24),
+                indexed: true,
+            },
+            EventParam {
+                name: "tickSpacing".to_string(),
+                kind: ParamType::Int(24),
+                indexed: false,
+            },
+            EventParam {
+                name: "pool".to_string(),
+                kind: ParamType::Address,
+                indexed: false,
+            },
+        ],
+        anonymous: false,
+    }
+}
+
+/// `Swap(address indexed sender, address indexed recipient, int256 amount0, int256 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick)`
+pub fn swap_event() -> Event {
+    Event {
+        name: "Swap".to_string(),
+        inputs: vec![
+            EventParam {
+                name: "sender".to_string(),
+                kind: ParamType::Address,
+                indexed: true,
+            },
+            EventParam {
+                name: "recipient".to_string(),
+                kind: ParamType::Address,
+                indexed: true,
+            },
+            EventParam {
+                name: "amount0".to_string(),
+                kind: ParamType::Int(256),
+                indexed: false,
+            },
+            EventParam {
+                name: "amount1".to_string(),
+                kind: ParamType::Int(256),
+                indexed: false,
+            },
+            EventParam {
+                name: "sqrtPriceX96".to_string(),
+                kind: ParamType::Uint(160),
+                indexed: false,
+            },
+            EventParam {
+                name: "liquidity".to_string(),
+                kind: ParamType::Uint(128),
+                indexed: false,
+            },
+            EventParam {
+                name: "tick".to_string(),
+                kind: ParamType::Int(24),
+                indexed: false,
+            },
+        ],
+        anonymous: false,
+    }
+}
+
+/// `Mint(address sender, address indexed owner, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1)`
+pub fn mint_event() -> Event {
+    Event {
+        name: "Mint".to_string(),
+        inputs: vec![
+            EventParam {
+                name: "sender".to_string(),
+                kind: ParamType::Address,
+                indexed: false,
+            },
+            EventParam {
+                name: "owner".to_string(),
+                kind: ParamType::Address,
+                indexed: true,
+            },
+            EventParam {
+                name: "tickLower".to_string(),
+                kind: ParamType::Int(24),
+                indexed: true,
+            },
+            EventParam {
+                name: "tickUpper".to_string(),
+                kind: ParamType::Int(24),
+                indexed: true,
+            },
+            EventParam {
+                name: "amount".to_string(),
+                kind: ParamType::Uint(128),
+                indexed: false,
+            },
+            EventParam {
+                name: "amount0".to_string(),
+                kind: ParamType::Uint(256),
+                indexed: false,
+            },
+            EventParam {
+                name: "amount1".to_string(),
+                kind: ParamType::Uint(256),
+                indexed: false,
+            },
+        ],
+        anonymous: false,
+    }
+}
+
+/// `Burn(address indexed owner, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1)`
+pub fn burn_event() -> Event {
+    Event {
+        name: "Burn".to_string(),
+        inputs: vec![
+            EventParam {
+                name: "owner".to_string(),
+                kind: ParamType::Address,
+                indexed: true,
+            },
+            EventParam {
+                name: "tickLower".to_string(),
+                kind: ParamType::Int(24),
+                indexed: true,
+            },
+            EventParam {
+                name: "tickUpper".to_string(),
+                kind: ParamType::Int(24),
+                indexed: true,
+            },
+            EventParam {
+                name: "amount".to_string(),
+                kind: ParamType::Uint(128),
+                indexed: false,
+            },
+            EventParam {
+                name: "amount0".to_string(),
+                kind: ParamType::Uint(256),
+                indexed: false,
+            },
+            EventParam {
+                name: "amount1".to_string(),
+                kind: ParamType::Uint(256),
+                indexed: false,
+            },
+        ],
+        anonymous: false,
+    }
+}
+
+/// Decodes a raw RPC log against `event`, shared by every event kind above.
+pub fn decode(event: &Event, log: &Log) -> Result<AbiLog, web3::ethabi::Error> {
+    event.parse_log(RawLog {
+        topics: log.topics.clone(),
+        data: log.data.clone().0,
+    })
+}
+
+/// The Keccak256 topic0 signature hash `event` would be logged under,
+/// used to tell which decoder a subscription's raw logs belong to.
+pub fn signature(event: &Event) -> web3::types::H256 {
+    event.signature()
+}
@@ -0,0 +1,383 @@
+//! Live indexing: once the historical backfill has caught up to chain
+//! head, keep watching for new `PoolCreated` logs and start decoding
+//! `Swap`/`Mint`/`Burn` logs for every pool discovered so far — the
+//! runtime equivalent of graph-node attaching handlers to dynamically
+//! created data sources.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use web3::ethabi::Log as AbiLog;
+use web3::futures::StreamExt;
+use web3::transports::DuplexTransport;
+use web3::types::{FilterBuilder, Log as RpcLog, H160, U256, U64};
+use web3::{Transport, Web3};
+
+use crate::sink::PoolSink;
+use crate::{events, get_token_info, wei_to_eth, PoolCreatedEvent, PoolInfo, TokenInfo};
+
+const SWAPS_DIR: &str = "swaps";
+const MINTS_DIR: &str = "mints";
+const BURNS_DIR: &str = "burns";
+
+#[derive(Debug, serde::Serialize)]
+struct SwapRow {
+    pool: H160,
+    sender: H160,
+    recipient: H160,
+    amount0: String,
+    amount1: String,
+    amount0_human: f64,
+    amount1_human: f64,
+    sqrt_price_x96: String,
+    liquidity: String,
+    tick: i64,
+    block_number: u64,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct MintRow {
+    pool: H160,
+    owner: H160,
+    tick_lower: i64,
+    tick_upper: i64,
+    amount: String,
+    amount0: String,
+    amount1: String,
+    amount0_human: f64,
+    amount1_human: f64,
+    block_number: u64,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct BurnRow {
+    pool: H160,
+    owner: H160,
+    tick_lower: i64,
+    tick_upper: i64,
+    amount: String,
+    amount0: String,
+    amount1: String,
+    amount0_human: f64,
+    amount1_human: f64,
+    block_number: u64,
+}
+
+/// ethabi has no signed-integer type: an `int256`/`int24` (or any other
+/// signed width) comes back as a full 256-bit `U256` that the Solidity
+/// encoder has already sign-extended, not a value truncated to its declared
+/// width. So a negative `int24` tick looks exactly like a negative `int256`
+/// amount — only bit 255 says whether it's negative, never the declared
+/// width. Splits it into a sign and an unsigned magnitude so callers can
+/// both render it exactly and scale it.
+fn decode_signed(raw: U256) -> (bool, U256) {
+    let sign_bit = U256::one() << 255;
+    if raw & sign_bit == U256::zero() {
+        (false, raw)
+    } else {
+        (true, U256::MAX - raw + U256::one())
+    }
+}
+
+/// Renders a sign-extended two's-complement word as a signed decimal string.
+fn signed_decimal(raw: U256) -> String {
+    let (negative, magnitude) = decode_signed(raw);
+    if negative {
+        format!("-{magnitude}")
+    } else {
+        magnitude.to_string()
+    }
+}
+
+/// Scales a sign-extended two's-complement token amount into human units
+/// using the token's `decimals`, matching `wei_to_eth` for unsigned amounts.
+fn signed_human(raw: U256, decimals: u8) -> f64 {
+    let (negative, magnitude) = decode_signed(raw);
+    let value = wei_to_eth(magnitude, decimals);
+    if negative {
+        -value
+    } else {
+        value
+    }
+}
+
+fn param(log: &AbiLog, name: &str) -> web3::ethabi::Token {
+    log.params
+        .iter()
+        .find(|p| p.name == name)
+        .unwrap_or_else(|| panic!("missing `{name}` in decoded log"))
+        .value
+        .clone()
+}
+
+/// One open `csv::Writer` per pool per event kind, so a live, potentially
+/// high-frequency log stream isn't paying for a file re-open on every row —
+/// the same fix commit 9cb65f0 applied to `CsvSink`.
+#[derive(Default)]
+struct PoolLogWriters {
+    swaps: HashMap<H160, csv::Writer<fs::File>>,
+    mints: HashMap<H160, csv::Writer<fs::File>>,
+    burns: HashMap<H160, csv::Writer<fs::File>>,
+}
+
+impl PoolLogWriters {
+    /// Appends `row` to `<dir>/<pool>.csv`, opening (and writing a header
+    /// for) the file only the first time this pool is seen in `writers`.
+    fn append_row<R: serde::Serialize>(
+        writers: &mut HashMap<H160, csv::Writer<fs::File>>,
+        dir: &str,
+        pool: H160,
+        row: &R,
+    ) {
+        let writer = writers.entry(pool).or_insert_with(|| {
+            fs::create_dir_all(dir).unwrap();
+            let path = format!("{dir}/{pool:#x}.csv");
+            let file_exists = Path::new(&path).exists();
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .unwrap();
+            csv::WriterBuilder::new()
+                .has_headers(!file_exists)
+                .from_writer(file)
+        });
+        writer.serialize(row).unwrap();
+        writer.flush().unwrap();
+    }
+}
+
+async fn subscribe_pool_logs<T>(
+    web3s: &Web3<T>,
+    pools: &[H160],
+) -> web3::api::SubscriptionStream<T, RpcLog>
+where
+    T: Transport + DuplexTransport,
+    T::Out: Send,
+{
+    let filter = FilterBuilder::default().address(pools.to_vec()).build();
+    web3s.eth_subscribe().subscribe_logs(filter).await.unwrap()
+}
+
+fn handle_pool_log(log: &RpcLog, decimals: (u8, u8), writers: &mut PoolLogWriters) {
+    let block_number = log.block_number.unwrap_or(U64::from(0)).as_u64();
+    let (decimals0, decimals1) = decimals;
+
+    if let Ok(decoded) = events::decode(&events::swap_event(), log) {
+        let amount0 = param(&decoded, "amount0").into_int().unwrap();
+        let amount1 = param(&decoded, "amount1").into_int().unwrap();
+        let row = SwapRow {
+            pool: log.address,
+            sender: param(&decoded, "sender").into_address().unwrap(),
+            recipient: param(&decoded, "recipient").into_address().unwrap(),
+            amount0: signed_decimal(amount0),
+            amount1: signed_decimal(amount1),
+            amount0_human: signed_human(amount0, decimals0),
+            amount1_human: signed_human(amount1, decimals1),
+            sqrt_price_x96: param(&decoded, "sqrtPriceX96")
+                .into_uint()
+                .unwrap()
+                .to_string(),
+            liquidity: param(&decoded, "liquidity")
+                .into_uint()
+                .unwrap()
+                .to_string(),
+            tick: signed_decimal(param(&decoded, "tick").into_int().unwrap())
+                .parse()
+                .unwrap_or_default(),
+            block_number,
+        };
+        PoolLogWriters::append_row(&mut writers.swaps, SWAPS_DIR, log.address, &row);
+        return;
+    }
+
+    if let Ok(decoded) = events::decode(&events::mint_event(), log) {
+        let amount0 = param(&decoded, "amount0").into_uint().unwrap();
+        let amount1 = param(&decoded, "amount1").into_uint().unwrap();
+        let row = MintRow {
+            pool: log.address,
+            owner: param(&decoded, "owner").into_address().unwrap(),
+            tick_lower: signed_decimal(param(&decoded, "tickLower").into_int().unwrap())
+                .parse()
+                .unwrap_or_default(),
+            tick_upper: signed_decimal(param(&decoded, "tickUpper").into_int().unwrap())
+                .parse()
+                .unwrap_or_default(),
+            amount: param(&decoded, "amount").into_uint().unwrap().to_string(),
+            amount0: amount0.to_string(),
+            amount1: amount1.to_string(),
+            amount0_human: wei_to_eth(amount0, decimals0),
+            amount1_human: wei_to_eth(amount1, decimals1),
+            block_number,
+        };
+        PoolLogWriters::append_row(&mut writers.mints, MINTS_DIR, log.address, &row);
+        return;
+    }
+
+    if let Ok(decoded) = events::decode(&events::burn_event(), log) {
+        let amount0 = param(&decoded, "amount0").into_uint().unwrap();
+        let amount1 = param(&decoded, "amount1").into_uint().unwrap();
+        let row = BurnRow {
+            pool: log.address,
+            owner: param(&decoded, "owner").into_address().unwrap(),
+            tick_lower: signed_decimal(param(&decoded, "tickLower").into_int().unwrap())
+                .parse()
+                .unwrap_or_default(),
+            tick_upper: signed_decimal(param(&decoded, "tickUpper").into_int().unwrap())
+                .parse()
+                .unwrap_or_default(),
+            amount: param(&decoded, "amount").into_uint().unwrap().to_string(),
+            amount0: amount0.to_string(),
+            amount1: amount1.to_string(),
+            amount0_human: wei_to_eth(amount0, decimals0),
+            amount1_human: wei_to_eth(amount1, decimals1),
+            block_number,
+        };
+        PoolLogWriters::append_row(&mut writers.burns, BURNS_DIR, log.address, &row);
+        return;
+    }
+
+    println!(
+        "unrecognized pool log from {:#x}, topic0={:?}",
+        log.address,
+        log.topics.first()
+    );
+}
+
+/// Runs until the process is killed: watches the factory for new pools and
+/// the growing pool set for `Swap`/`Mint`/`Burn` activity, re-subscribing
+/// to the latter every time a new pool is discovered.
+pub async fn stream_live<T>(
+    web3s: Web3<T>,
+    factory_address: H160,
+    known_pools: Vec<PoolInfo>,
+    mut sink: Box<dyn PoolSink>,
+) where
+    T: Transport + DuplexTransport,
+    T::Out: Send,
+{
+    // Carried over from the persisted sink, this is what lets a pool's
+    // `Swap`/`Mint`/`Burn` amounts be scaled correctly even across a
+    // restart, without re-fetching its tokens' metadata.
+    let mut pool_decimals: HashMap<H160, (u8, u8)> = known_pools
+        .iter()
+        .map(|pool| (pool.pool_addr, (pool.token0_decimals, pool.token1_decimals)))
+        .collect();
+    let mut pool_addrs: Vec<H160> = known_pools.iter().map(|pool| pool.pool_addr).collect();
+
+    let pool_created = events::pool_created_event();
+    let factory_filter = FilterBuilder::default()
+        .address(vec![factory_address])
+        .build();
+    let mut new_pools_sub = web3s
+        .eth_subscribe()
+        .subscribe_logs(factory_filter)
+        .await
+        .unwrap();
+
+    let mut pool_logs_sub = subscribe_pool_logs(&web3s, &pool_addrs).await;
+    let mut token_infos: HashMap<H160, TokenInfo> = HashMap::new();
+    let mut log_writers = PoolLogWriters::default();
+
+    loop {
+        tokio::select! {
+            maybe_log = new_pools_sub.next() => {
+                let Some(log_result) = maybe_log else { break };
+                let log = match log_result {
+                    Ok(log) => log,
+                    Err(err) => { println!("{err}"); continue; }
+                };
+                let decoded = match events::decode(&pool_created, &log) {
+                    Ok(decoded) => decoded,
+                    Err(err) => { println!("{err}"); continue; }
+                };
+                let mut pce = PoolCreatedEvent::from_log(decoded);
+                pce.block_number = log.block_number.unwrap_or(U64::from(0));
+                for token in [pce.token0, pce.token1] {
+                    if !token_infos.contains_key(&token) {
+                        token_infos.insert(token, get_token_info(web3s.clone(), token).await);
+                    }
+                }
+                let new_pool = pce.pool;
+                let pool_info = PoolInfo::from_pool_created_event(pce, &token_infos);
+                pool_decimals.insert(
+                    new_pool,
+                    (pool_info.token0_decimals, pool_info.token1_decimals),
+                );
+                sink.write(&pool_info);
+                sink.flush();
+
+                pool_addrs.push(new_pool);
+                pool_logs_sub = subscribe_pool_logs(&web3s, &pool_addrs).await;
+            }
+            maybe_log = pool_logs_sub.next() => {
+                let Some(log_result) = maybe_log else { continue };
+                match log_result {
+                    Ok(log) => {
+                        let decimals = pool_decimals.get(&log.address).copied().unwrap_or((18, 18));
+                        handle_pool_log(&log, decimals, &mut log_writers);
+                    }
+                    Err(err) => println!("{err}"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A two's-complement encoding of `value` as ethabi would hand it back
+    /// for a signed type of any width: the full 256-bit sign-extended word,
+    /// not one truncated to the type's declared bit width.
+    fn encode_signed(value: i64) -> U256 {
+        if value >= 0 {
+            U256::from(value)
+        } else {
+            U256::MAX - U256::from(-value) + U256::one()
+        }
+    }
+
+    #[test]
+    fn decode_signed_positive() {
+        assert_eq!(decode_signed(U256::from(12345)), (false, U256::from(12345)));
+        assert_eq!(decode_signed(U256::zero()), (false, U256::zero()));
+    }
+
+    #[test]
+    fn decode_signed_negative_tick() {
+        // An ordinary negative int24 tick, e.g. -5, still arrives as a
+        // full-width two's-complement word rather than one bounded to 24
+        // bits — this used to underflow and panic (fixed in 851a3c8).
+        let raw = encode_signed(-5);
+        assert_eq!(decode_signed(raw), (true, U256::from(5)));
+        assert_eq!(signed_decimal(raw), "-5");
+    }
+
+    #[test]
+    fn decode_signed_negative_amount() {
+        let raw = encode_signed(-123_456_789);
+        assert_eq!(decode_signed(raw), (true, U256::from(123_456_789)));
+        assert_eq!(signed_decimal(raw), "-123456789");
+    }
+
+    #[test]
+    fn signed_decimal_roundtrips_min_and_max_word() {
+        assert_eq!(decode_signed(U256::MAX), (true, U256::one()));
+        assert_eq!(signed_decimal(U256::MAX), "-1");
+
+        let most_negative = U256::one() << 255;
+        assert_eq!(decode_signed(most_negative), (true, most_negative));
+    }
+
+    #[test]
+    fn signed_human_scales_by_decimals() {
+        let raw = encode_signed(-5_000_000);
+        assert!((signed_human(raw, 6) - (-5.0)).abs() < 1e-9);
+
+        let positive = U256::from(2_500_000_000_000_000_000u128);
+        assert!((signed_human(positive, 18) - 2.5).abs() < 1e-9);
+    }
+}
@@ -1,28 +1,62 @@
-use std::any::Any;
 use std::collections::HashMap;
+use std::fs;
 use std::str::FromStr;
-use std::{env, fs, os};
 
-use csv::Writer;
-use web3::contract::tokens::{Detokenize, Tokenize};
 use web3::contract::{Contract, Options};
 use web3::Web3;
 
-use web3::ethabi::{
-    Contract as AbiContract, Event, EventParam, Int, Log, ParamType, RawLog, Token, Topic, Uint,
-};
-use web3::futures::FutureExt;
-use web3::futures::{future, Future, Stream, StreamExt};
-use web3::transports::WebSocket;
+use web3::ethabi::Log;
+use web3::futures::StreamExt;
 use web3::types::{Address, FilterBuilder, H160, H256, U256, U64};
+use web3::Transport;
 
-fn wei_to_eth(wei_val: U256) -> f64 {
+mod events;
+mod live;
+mod quorum;
+mod sink;
+mod transport;
+
+use sink::PoolSink;
+use transport::build_transport;
+
+/// Genesis block of the Uniswap v3 factory on mainnet; the scan starts here
+/// when no cursor file is present yet.
+const FACTORY_GENESIS_BLOCK: u64 = 12369621;
+
+/// Number of blocks fetched per `eth_getLogs` window. Keeping this bounded
+/// means a crash mid-run only loses the logs in the current window instead
+/// of the whole historical scan.
+const BLOCK_WINDOW_SIZE: u64 = 2000;
+
+/// Sidecar file holding the highest fully-processed block number.
+const CURSOR_FILE: &str = "pools.cursor";
+
+/// Number of concurrent in-flight `get_token_info` calls per window.
+const TOKEN_FETCH_CONCURRENCY: usize = 20;
+
+pub(crate) const POOLS_CSV: &str = "pools.csv";
+
+/// Reads the last persisted cursor, if any. Absence of the file means this
+/// is the first run, so the caller should fall back to the factory genesis
+/// block.
+fn read_cursor() -> Option<u64> {
+    let contents = fs::read_to_string(CURSOR_FILE).ok()?;
+    contents.trim().parse::<u64>().ok()
+}
+
+/// Persists `block` as the highest fully-processed block. Must only be
+/// called after the corresponding rows have been flushed to `pools.csv`.
+fn write_cursor(block: u64) {
+    fs::write(CURSOR_FILE, block.to_string()).unwrap();
+}
+
+fn wei_to_eth(wei_val: U256, decimals: u8) -> f64 {
     let res = wei_val.as_u128() as f64;
-    res / 1_000_000_000_000_000_000.0
+    res / 10f64.powi(decimals as i32)
 }
 
-#[derive(Debug, serde::Serialize)]
-struct PoolInfo {
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct PoolInfo {
     pub pool_addr: H160,
     pub token0_name: String,
     pub token0_symbol: String,
@@ -31,6 +65,8 @@ struct PoolInfo {
     pub fee: usize,
     pub token0_addr: H160,
     pub token1_addr: H160,
+    pub token0_decimals: u8,
+    pub token1_decimals: u8,
     pub block_number: u64,
 }
 
@@ -39,36 +75,32 @@ impl PoolInfo {
         event: PoolCreatedEvent,
         token_infos: &HashMap<H160, TokenInfo>,
     ) -> PoolInfo {
+        let token0 = token_infos.get(&event.token0).unwrap();
+        let token1 = token_infos.get(&event.token1).unwrap();
         PoolInfo {
             pool_addr: event.pool,
-            token0_name: token_infos.get(&event.token0.clone()).unwrap().name.clone(),
-            token1_name: token_infos.get(&event.token1.clone()).unwrap().name.clone(),
-            token0_symbol: token_infos
-                .get(&event.token0.clone())
-                .unwrap()
-                .symbol
-                .clone(),
-            token1_symbol: token_infos
-                .get(&event.token1.clone())
-                .unwrap()
-                .symbol
-                .clone(),
+            token0_name: token0.name.clone(),
+            token1_name: token1.name.clone(),
+            token0_symbol: token0.symbol.clone(),
+            token1_symbol: token1.symbol.clone(),
             fee: event.fee,
             token0_addr: event.token0,
             token1_addr: event.token1,
+            token0_decimals: token0.decimals,
+            token1_decimals: token1.decimals,
             block_number: event.block_number.as_u64(),
         }
     }
 }
 
 #[derive(Debug, Default)]
-struct PoolCreatedEvent {
-    token0: H160,
-    token1: H160,
+pub(crate) struct PoolCreatedEvent {
+    pub(crate) token0: H160,
+    pub(crate) token1: H160,
     fee: usize,
     tick_spacing: usize,
-    pool: H160,
-    block_number: U64,
+    pub(crate) pool: H160,
+    pub(crate) block_number: U64,
 }
 
 impl PoolCreatedEvent {
@@ -90,118 +122,175 @@ impl PoolCreatedEvent {
     }
 }
 
-struct TokenInfo {
+pub(crate) struct TokenInfo {
     name: String,
     symbol: String,
     address: H160,
-    // decimals: U256,
+    decimals: u8,
+}
+
+/// Some pre-ERC20-standardization tokens (MKR being the canonical example)
+/// return `name`/`symbol` as `bytes32` instead of `string`, which makes the
+/// `string`-typed ABI call in `token_abi.json` revert outright. When that
+/// happens, retry against `token_abi_bytes32.json` and decode the fixed-size
+/// return value by trimming its trailing NUL padding.
+async fn token_string_field<T: Transport>(
+    string_contract: &Contract<T>,
+    bytes32_contract: &Contract<T>,
+    function: &str,
+) -> String
+where
+    T::Out: Send,
+{
+    if let Ok(value) = string_contract
+        .query::<String, _, _, _>(function, (), None, Options::default(), None)
+        .await
+    {
+        return value;
+    }
+    match bytes32_contract
+        .query::<H256, _, _, _>(function, (), None, Options::default(), None)
+        .await
+    {
+        Ok(raw) => String::from_utf8_lossy(raw.as_bytes())
+            .trim_end_matches('\0')
+            .to_string(),
+        Err(_) => String::new(),
+    }
 }
 
-async fn get_token_info(web3s: Web3<WebSocket>, addr: H160) -> TokenInfo {
+pub(crate) async fn get_token_info<T: Transport>(web3s: Web3<T>, addr: H160) -> TokenInfo
+where
+    T::Out: Send,
+{
     let token_contract =
         Contract::from_json(web3s.eth(), addr, include_bytes!("token_abi.json")).unwrap();
-    async fn caller<T: Detokenize + Default>(contract: &Contract<WebSocket>, name: &str) -> T {
-        let token_data: T = contract
-            .query(name, (), None, Options::default(), None)
-            .await
-            .unwrap_or_default();
-        token_data
-    }
-    let token_name: String = caller(&token_contract, "name").await;
-    let token_symbol: String = caller(&token_contract, "symbol").await;
-    // let decimals: U256 = caller(&token_contract, "decimals").await;
+    let token_contract_bytes32 =
+        Contract::from_json(web3s.eth(), addr, include_bytes!("token_abi_bytes32.json")).unwrap();
+
+    let token_name = token_string_field(&token_contract, &token_contract_bytes32, "name").await;
+    let token_symbol = token_string_field(&token_contract, &token_contract_bytes32, "symbol").await;
+    // A non-compliant token with no `decimals` at all is rare enough that
+    // assuming the common case (18) is a reasonable fallback. `low_u32`
+    // (rather than `as_u32`, which panics above 2^32-1) keeps a malformed
+    // response from taking down the whole run too.
+    let decimals: u8 = token_contract
+        .query::<U256, _, _, _>("decimals", (), None, Options::default(), None)
+        .await
+        .map(|d| d.low_u32() as u8)
+        .unwrap_or(18);
     TokenInfo {
         name: token_name,
         address: addr,
-        // decimals,
+        decimals,
         symbol: token_symbol,
     }
 }
 
-async fn parse_logs_data(web3s: Web3<WebSocket>, address: H160) {
-    let filter = FilterBuilder::default()
-        .address(vec![address])
-        .from_block(12369621.into()) // genesis block of uniswap factory
-        .build();
-    let params = vec![
-        EventParam {
-            name: "token0".to_string(),
-            kind: ParamType::Address,
-            indexed: true,
-        },
-        EventParam {
-            name: "token1".to_string(),
-            kind: ParamType::Address,
-            indexed: true,
-        },
-        EventParam {
-            name: "fee".to_string(),
-            kind: ParamType::Uint(24),
-            indexed: true,
-        },
-        EventParam {
-            name: "tickSpacing".to_string(),
-            kind: ParamType::Int(24),
-            indexed: false,
-        },
-        EventParam {
-            name: "pool".to_string(),
-            kind: ParamType::Address,
-            indexed: false,
-        },
-    ];
-    let event = Event {
-        name: "PoolCreated".to_string(),
-        inputs: params,
-        anonymous: false,
-    };
-    let logs = web3s.eth_filter().create_logs_filter(filter).await.unwrap();
-    let l = logs.logs().await.unwrap();
+async fn parse_logs_data<T: Transport>(web3s: Web3<T>, address: H160, sink: &mut dyn PoolSink)
+where
+    T::Out: Send,
+{
+    let event = events::pool_created_event();
+
+    let mut from_block = read_cursor().map_or(FACTORY_GENESIS_BLOCK, |cursor| cursor + 1);
     let mut token_infos: HashMap<H160, TokenInfo> = HashMap::new();
-    let mut writer = Writer::from_writer(vec![]);
-    let total_len = l.len();
-    println!("total_len: {total_len}");
-    for (i, log) in l.into_iter().enumerate() {
-        let processed = i as f64 / total_len as f64;
-        if i % 10 == 0 {
-            println!("processed {processed}");
+
+    // Re-read the chain head every window instead of once up front: a real
+    // backfill from genesis runs long enough for the head to move well past
+    // a stale snapshot, which would otherwise leave a silent gap between
+    // wherever the loop stopped and wherever `stream_live`'s subscription
+    // picks up.
+    loop {
+        let latest_block = web3s.eth().block_number().await.unwrap().as_u64();
+        if from_block > latest_block {
+            break;
         }
-        let lr = event.parse_log(RawLog {
-            topics: log.topics.clone(),
-            data: log.data.clone().0,
-        });
-        let l = match lr {
-            Ok(l) => l,
-            Err(err) => {
-                println!("{err}");
-                continue;
-            }
-        };
-        let mut pce = PoolCreatedEvent::from_log(l);
-        pce.block_number = log.block_number.unwrap_or(U64::from(0));
-        if !token_infos.contains_key(&pce.token0) {
-            token_infos.insert(pce.token0, get_token_info(web3s.clone(), pce.token0).await);
+        let to_block = (from_block + BLOCK_WINDOW_SIZE - 1).min(latest_block);
+        println!("scanning blocks {from_block}..={to_block} (head {latest_block})");
+
+        let filter = FilterBuilder::default()
+            .address(vec![address])
+            .from_block(from_block.into())
+            .to_block(to_block.into())
+            .build();
+        let logs = web3s.eth_filter().create_logs_filter(filter).await.unwrap();
+        let l = logs.logs().await.unwrap();
+
+        let total_len = l.len();
+        println!("window total_len: {total_len}");
+        let mut pool_events = Vec::with_capacity(total_len);
+        for log in l.into_iter() {
+            let lr = events::decode(&event, &log);
+            let l = match lr {
+                Ok(l) => l,
+                Err(err) => {
+                    println!("{err}");
+                    continue;
+                }
+            };
+            let mut pce = PoolCreatedEvent::from_log(l);
+            pce.block_number = log.block_number.unwrap_or(U64::from(0));
+            pool_events.push(pce);
+        }
+
+        // Fetch metadata for every distinct token discovered in this window
+        // concurrently instead of awaiting one `get_token_info` at a time.
+        let missing_tokens: Vec<H160> = pool_events
+            .iter()
+            .flat_map(|pce| [pce.token0, pce.token1])
+            .filter(|addr| !token_infos.contains_key(addr))
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        let fetched: Vec<TokenInfo> = web3::futures::stream::iter(missing_tokens)
+            .map(|addr| get_token_info(web3s.clone(), addr))
+            .buffer_unordered(TOKEN_FETCH_CONCURRENCY)
+            .collect()
+            .await;
+        for token_info in fetched {
+            token_infos.insert(token_info.address, token_info);
         }
-        if !token_infos.contains_key(&pce.token1) {
-            token_infos.insert(pce.token1, get_token_info(web3s.clone(), pce.token1).await);
+
+        for (i, pce) in pool_events.into_iter().enumerate() {
+            if i % 10 == 0 {
+                let processed = i as f64 / total_len.max(1) as f64;
+                println!("processed {processed}");
+            }
+            let pool_info = PoolInfo::from_pool_created_event(pce, &token_infos);
+            sink.write(&pool_info);
         }
-        let pool_info = PoolInfo::from_pool_created_event(pce, &token_infos);
-        writer.serialize(pool_info).unwrap();
+        sink.flush();
+
+        // Only advance the cursor once this window's rows are durably on
+        // disk, so a crash mid-window just re-scans the same range.
+        write_cursor(to_block);
+        from_block = to_block + 1;
     }
-    let csv_data = String::from_utf8(writer.into_inner().unwrap()).unwrap();
-    fs::write("pools.csv", csv_data).unwrap();
 }
 
 #[tokio::main]
 async fn main() -> web3::Result<()> {
     dotenv::dotenv().ok();
 
-    let websocket = web3::transports::WebSocket::new(&env::var("INFURA_URL").unwrap()).await?;
-    let web3s = web3::Web3::new(websocket);
-    parse_logs_data(
-        web3s.clone(),
-        Address::from_str("0x1F98431c8aD98523631AE4a59f267346ea31F984").unwrap(),
-    )
-    .await;
+    let factory_address = Address::from_str("0x1F98431c8aD98523631AE4a59f267346ea31F984").unwrap();
+
+    let mut sink = sink::build_sink();
+
+    let transport = build_transport().await?;
+    let web3s = web3::Web3::new(transport);
+    parse_logs_data(web3s.clone(), factory_address, sink.as_mut()).await;
+
+    // `parse_logs_data` already wrote (and flushed) every discovered pool
+    // through `sink`, so reading it back covers both this run's pools and
+    // any from earlier, interrupted runs.
+    let known_pools = sink.known_pools();
+
+    // Caught up to chain head: switch to a live subscription for new pools
+    // plus Swap/Mint/Burn activity on everything discovered so far.
+    let live_transport = transport::build_subscribable_transport().await?;
+    let live_web3s = web3::Web3::new(live_transport);
+    live::stream_live(live_web3s, factory_address, known_pools, sink).await;
+
     Ok(())
 }
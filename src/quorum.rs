@@ -0,0 +1,110 @@
+//! A `Transport` that fans a JSON-RPC request out to several underlying
+//! endpoints, mirroring ethers-rs's `quorum` transport. Guards against a
+//! single node serving stale or inconsistent logs during a long historical
+//! scan.
+
+use futures::future::{join_all, BoxFuture};
+use futures::FutureExt;
+use jsonrpc_core::Call;
+use serde_json::Value;
+use web3::error::{Error, TransportError};
+use web3::{RequestId, Transport};
+
+/// How many times to re-send to every endpoint before giving up, so a
+/// transient error or a one-off disagreement doesn't fail the whole
+/// request the first time it's seen.
+const MAX_ATTEMPTS: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuorumMode {
+    /// Accept the first endpoint to answer successfully; the rest are not
+    /// required to agree.
+    Fallback,
+    /// Require at least `threshold` endpoints to return the same value
+    /// before accepting it, retrying the remaining endpoints on mismatch.
+    Quorum { threshold: usize },
+}
+
+/// Sends every request to all configured endpoints and reconciles the
+/// responses according to `mode`.
+#[derive(Clone, Debug)]
+pub struct QuorumTransport<T> {
+    endpoints: Vec<T>,
+    mode: QuorumMode,
+}
+
+impl<T: Transport> QuorumTransport<T> {
+    pub fn new(endpoints: Vec<T>, mode: QuorumMode) -> Self {
+        assert!(
+            !endpoints.is_empty(),
+            "quorum transport needs at least one endpoint"
+        );
+        QuorumTransport { endpoints, mode }
+    }
+}
+
+impl<T> Transport for QuorumTransport<T>
+where
+    T: Transport + Clone + Send + Sync + 'static,
+    T::Out: Send + 'static,
+{
+    type Out = BoxFuture<'static, web3::error::Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        // Every endpoint builds an identical request for the same
+        // method/params, so preparing against the first one is enough.
+        self.endpoints[0].prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call) -> Self::Out {
+        let mode = self.mode;
+        let endpoints = self.endpoints.clone();
+
+        async move {
+            let mut last_err =
+                Error::Transport(TransportError::Message("all providers failed".to_string()));
+
+            // Re-send to every endpoint on mismatch/error instead of failing
+            // outright on the first round, so a single down endpoint or one
+            // transient disagreement doesn't take down the whole request.
+            for attempt in 1..=MAX_ATTEMPTS {
+                let calls: Vec<_> = endpoints
+                    .iter()
+                    .map(|endpoint| endpoint.send(id, request.clone()))
+                    .collect();
+                let results = join_all(calls).await;
+
+                match mode {
+                    QuorumMode::Fallback => {
+                        if let Some(value) = results.into_iter().find_map(Result::ok) {
+                            return Ok(value);
+                        }
+                        last_err = Error::Transport(TransportError::Message(format!(
+                            "all providers failed (attempt {attempt}/{MAX_ATTEMPTS})"
+                        )));
+                    }
+                    QuorumMode::Quorum { threshold } => {
+                        let mut tally: Vec<(Value, usize)> = Vec::new();
+                        for result in results.into_iter().flatten() {
+                            match tally.iter_mut().find(|(seen, _)| *seen == result) {
+                                Some(entry) => entry.1 += 1,
+                                None => tally.push((result, 1)),
+                            }
+                        }
+                        if let Some((value, _)) =
+                            tally.into_iter().find(|(_, count)| *count >= threshold)
+                        {
+                            return Ok(value);
+                        }
+                        last_err = Error::Transport(TransportError::Message(format!(
+                            "no {threshold} providers agreed on a response (attempt {attempt}/{MAX_ATTEMPTS})"
+                        )));
+                    }
+                }
+            }
+
+            Err(last_err)
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,193 @@
+//! Where discovered pools end up. `parse_logs_data` and live mode write
+//! through a `PoolSink` instead of hard-coding a CSV writer, so a big
+//! factory doesn't have to be re-parsed just to run a `SELECT` over it.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use rusqlite::{params, Connection};
+use web3::types::H160;
+
+use crate::{PoolInfo, POOLS_CSV};
+
+/// Selects which sink `build_sink` constructs. `sqlite:<path>` opens (and
+/// creates if needed) a SQLite database at `<path>`; anything else, or an
+/// unset env var, keeps writing `pools.csv`.
+const POOL_SINK_VAR: &str = "POOL_SINK";
+
+pub trait PoolSink {
+    /// Upserts `pool` keyed by `pool_addr`, so re-processing a window after
+    /// a crash is idempotent.
+    fn write(&mut self, pool: &PoolInfo);
+
+    /// Durably persists everything written since the last call.
+    fn flush(&mut self);
+
+    /// Every pool persisted so far, used to seed live mode (across
+    /// restarts) without re-scanning history. Returning full rows, not just
+    /// addresses, lets live mode scale `Swap`/`Mint`/`Burn` amounts for
+    /// carried-over pools without re-fetching token metadata.
+    fn known_pools(&mut self) -> Vec<PoolInfo>;
+}
+
+pub struct CsvSink {
+    path: String,
+    writer: csv::Writer<fs::File>,
+}
+
+impl CsvSink {
+    pub fn new(path: &str) -> Self {
+        let file_exists = Path::new(path).exists();
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        let writer = csv::WriterBuilder::new()
+            .has_headers(!file_exists)
+            .from_writer(file);
+        CsvSink {
+            path: path.to_string(),
+            writer,
+        }
+    }
+}
+
+impl PoolSink for CsvSink {
+    fn write(&mut self, pool: &PoolInfo) {
+        // A plain CSV has no primary key to upsert on, so a crash between
+        // writing a row and advancing the cursor can reprocess (and thus
+        // duplicate) it; the SQLite sink is the idempotent option.
+        self.writer.serialize(pool).unwrap();
+    }
+
+    fn flush(&mut self) {
+        // One writer/file handle lives for the whole run; only flush here,
+        // not on every `write`, so a large factory backfill isn't paying
+        // for a re-open and fsync per row.
+        self.writer.flush().unwrap();
+    }
+
+    fn known_pools(&mut self) -> Vec<PoolInfo> {
+        let Ok(mut reader) = csv::Reader::from_path(&self.path) else {
+            return Vec::new();
+        };
+        reader
+            .deserialize::<PoolInfo>()
+            .filter_map(|row| row.ok())
+            .collect()
+    }
+}
+
+pub struct SqliteSink {
+    conn: Connection,
+}
+
+impl SqliteSink {
+    pub fn new(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS pools (
+                pool_addr     TEXT PRIMARY KEY,
+                token0_name   TEXT NOT NULL,
+                token0_symbol TEXT NOT NULL,
+                token1_name   TEXT NOT NULL,
+                token1_symbol TEXT NOT NULL,
+                fee           INTEGER NOT NULL,
+                token0_addr   TEXT NOT NULL,
+                token1_addr   TEXT NOT NULL,
+                token0_decimals INTEGER NOT NULL,
+                token1_decimals INTEGER NOT NULL,
+                block_number  INTEGER NOT NULL
+            )",
+        )?;
+        Ok(SqliteSink { conn })
+    }
+}
+
+impl PoolSink for SqliteSink {
+    fn write(&mut self, pool: &PoolInfo) {
+        self.conn
+            .execute(
+                "INSERT INTO pools
+                    (pool_addr, token0_name, token0_symbol, token1_name, token1_symbol, fee, token0_addr, token1_addr, token0_decimals, token1_decimals, block_number)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                 ON CONFLICT(pool_addr) DO UPDATE SET
+                    token0_name = excluded.token0_name,
+                    token0_symbol = excluded.token0_symbol,
+                    token1_name = excluded.token1_name,
+                    token1_symbol = excluded.token1_symbol,
+                    fee = excluded.fee,
+                    token0_addr = excluded.token0_addr,
+                    token1_addr = excluded.token1_addr,
+                    token0_decimals = excluded.token0_decimals,
+                    token1_decimals = excluded.token1_decimals,
+                    block_number = excluded.block_number",
+                params![
+                    format!("{:#x}", pool.pool_addr),
+                    &pool.token0_name,
+                    &pool.token0_symbol,
+                    &pool.token1_name,
+                    &pool.token1_symbol,
+                    pool.fee as i64,
+                    format!("{:#x}", pool.token0_addr),
+                    format!("{:#x}", pool.token1_addr),
+                    pool.token0_decimals as i64,
+                    pool.token1_decimals as i64,
+                    pool.block_number as i64,
+                ],
+            )
+            .unwrap();
+    }
+
+    fn flush(&mut self) {
+        // Each `write` already runs (and commits) its own statement, so
+        // there is nothing buffered to flush.
+    }
+
+    fn known_pools(&mut self) -> Vec<PoolInfo> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT pool_addr, token0_name, token0_symbol, token1_name, token1_symbol,
+                        fee, token0_addr, token1_addr, token0_decimals, token1_decimals, block_number
+                 FROM pools",
+            )
+            .unwrap();
+        stmt.query_map([], |row| {
+            Ok(PoolInfo {
+                pool_addr: H160::from_str(row.get::<_, String>(0)?.trim_start_matches("0x"))
+                    .unwrap_or_default(),
+                token0_name: row.get(1)?,
+                token0_symbol: row.get(2)?,
+                token1_name: row.get(3)?,
+                token1_symbol: row.get(4)?,
+                fee: row.get::<_, i64>(5)? as usize,
+                token0_addr: H160::from_str(row.get::<_, String>(6)?.trim_start_matches("0x"))
+                    .unwrap_or_default(),
+                token1_addr: H160::from_str(row.get::<_, String>(7)?.trim_start_matches("0x"))
+                    .unwrap_or_default(),
+                token0_decimals: row.get::<_, i64>(8)? as u8,
+                token1_decimals: row.get::<_, i64>(9)? as u8,
+                block_number: row.get::<_, i64>(10)? as u64,
+            })
+        })
+        .unwrap()
+        .filter_map(|row| row.ok())
+        .collect()
+    }
+}
+
+/// Builds the configured sink: `POOL_SINK=sqlite:<path>` opens a SQLite
+/// database, anything else (including unset) falls back to `pools.csv`.
+pub fn build_sink() -> Box<dyn PoolSink> {
+    match env::var(POOL_SINK_VAR) {
+        Ok(spec) => match spec.strip_prefix("sqlite:") {
+            Some(path) => Box::new(SqliteSink::new(path).unwrap()),
+            None => Box::new(CsvSink::new(POOLS_CSV)),
+        },
+        Err(_) => Box::new(CsvSink::new(POOLS_CSV)),
+    }
+}
@@ -0,0 +1,170 @@
+//! Transport selection: talk to a node over WebSocket (e.g. Infura) or,
+//! for a locally-run archive node, over IPC (a Unix domain socket on
+//! Linux/macOS, a named pipe on Windows) to avoid per-request HTTP/WS
+//! overhead and provider rate limits.
+
+use std::env;
+
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::FutureExt;
+use jsonrpc_core::Call;
+use serde_json::Value;
+use web3::transports::{DuplexTransport, Ipc, WebSocket};
+use web3::types::SubscriptionId;
+use web3::{Error, RequestId, Transport};
+
+use crate::quorum::{QuorumMode, QuorumTransport};
+
+/// Env var pointing at a local IPC endpoint (Unix socket path / Windows
+/// named pipe). Takes precedence over `INFURA_URL` when set.
+const ETH_IPC_PATH_VAR: &str = "ETH_IPC_PATH";
+
+/// Env var pointing at a WebSocket endpoint, e.g. an Infura project URL.
+const INFURA_URL_VAR: &str = "INFURA_URL";
+
+/// Comma-separated list of endpoints (WebSocket URLs and/or IPC paths) to
+/// query as a fallback/quorum group. Overrides `ETH_IPC_PATH`/`INFURA_URL`
+/// when set.
+const ETH_RPC_ENDPOINTS_VAR: &str = "ETH_RPC_ENDPOINTS";
+
+/// Minimum number of endpoints that must agree before a response from
+/// `ETH_RPC_ENDPOINTS` is accepted. Falls back to "first success wins" when
+/// unset or `1`.
+const ETH_QUORUM_THRESHOLD_VAR: &str = "ETH_QUORUM_THRESHOLD";
+
+/// A transport that is either a WebSocket or an IPC connection, so the rest
+/// of the indexer can stay generic over `web3::Transport` instead of being
+/// nailed to `Web3<WebSocket>`.
+#[derive(Clone, Debug)]
+pub enum EthTransport {
+    WebSocket(WebSocket),
+    Ipc(Ipc),
+}
+
+impl Transport for EthTransport {
+    type Out = BoxFuture<'static, web3::error::Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        match self {
+            EthTransport::WebSocket(ws) => ws.prepare(method, params),
+            EthTransport::Ipc(ipc) => ipc.prepare(method, params),
+        }
+    }
+
+    fn send(&self, id: RequestId, request: Call) -> Self::Out {
+        match self {
+            EthTransport::WebSocket(ws) => ws.send(id, request).boxed(),
+            EthTransport::Ipc(ipc) => ipc.send(id, request).boxed(),
+        }
+    }
+}
+
+impl DuplexTransport for EthTransport {
+    type NotificationStream = BoxStream<'static, Value>;
+
+    fn subscribe(&self, id: SubscriptionId) -> Self::NotificationStream {
+        match self {
+            EthTransport::WebSocket(ws) => ws.subscribe(id).boxed(),
+            EthTransport::Ipc(ipc) => ipc.subscribe(id).boxed(),
+        }
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) {
+        match self {
+            EthTransport::WebSocket(ws) => ws.unsubscribe(id),
+            EthTransport::Ipc(ipc) => ipc.unsubscribe(id),
+        }
+    }
+}
+
+/// Builds a single endpoint's transport, picking WebSocket vs IPC from the
+/// shape of the endpoint string: a `ws://`/`wss://`/`http` URL is a
+/// WebSocket endpoint, anything else is treated as an IPC socket/pipe path.
+async fn build_single_transport(endpoint: &str) -> web3::Result<EthTransport> {
+    if endpoint.starts_with("ws://")
+        || endpoint.starts_with("wss://")
+        || endpoint.starts_with("http")
+    {
+        let ws = WebSocket::new(endpoint).await?;
+        Ok(EthTransport::WebSocket(ws))
+    } else {
+        let ipc = Ipc::new(endpoint).await?;
+        Ok(EthTransport::Ipc(ipc))
+    }
+}
+
+/// Builds the provider used for the whole indexing run.
+///
+/// `ETH_RPC_ENDPOINTS` (a comma-separated list) takes precedence and is
+/// wired up as a fallback/quorum group, guarding against a single node
+/// serving stale or inconsistent logs; `ETH_QUORUM_THRESHOLD` controls how
+/// many of those endpoints must agree. Otherwise falls back to a single
+/// endpoint chosen via `ETH_IPC_PATH` (IPC) or `INFURA_URL` (WebSocket).
+pub async fn build_transport() -> web3::Result<QuorumTransport<EthTransport>> {
+    if let Ok(endpoints) = env::var(ETH_RPC_ENDPOINTS_VAR) {
+        let endpoint_list: Vec<&str> = endpoints
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .collect();
+        let mut transports = Vec::with_capacity(endpoint_list.len());
+        for endpoint in endpoint_list {
+            transports.push(build_single_transport(endpoint).await?);
+        }
+        let mode = match env::var(ETH_QUORUM_THRESHOLD_VAR)
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+        {
+            Some(threshold) if threshold > 1 => QuorumMode::Quorum { threshold },
+            _ => QuorumMode::Fallback,
+        };
+        return Ok(QuorumTransport::new(transports, mode));
+    }
+
+    Ok(QuorumTransport::new(
+        vec![build_configured_transport().await?],
+        QuorumMode::Fallback,
+    ))
+}
+
+/// Builds the single endpoint chosen via `ETH_IPC_PATH`/`INFURA_URL`, or (if
+/// only `ETH_RPC_ENDPOINTS` is set) the first entry of that list — otherwise
+/// a deployment configured solely via `ETH_RPC_ENDPOINTS` would backfill
+/// successfully but then fail to ever reach live mode.
+async fn build_configured_transport() -> web3::Result<EthTransport> {
+    if let Ok(ipc_path) = env::var(ETH_IPC_PATH_VAR) {
+        let ipc = Ipc::new(&ipc_path).await?;
+        return Ok(EthTransport::Ipc(ipc));
+    }
+
+    if let Ok(infura_url) = env::var(INFURA_URL_VAR) {
+        let ws = WebSocket::new(&infura_url).await?;
+        return Ok(EthTransport::WebSocket(ws));
+    }
+
+    let first_endpoint = env::var(ETH_RPC_ENDPOINTS_VAR)
+        .ok()
+        .and_then(|endpoints| {
+            endpoints
+                .split(',')
+                .map(str::trim)
+                .find(|e| !e.is_empty())
+                .map(str::to_string)
+        })
+        .ok_or_else(|| {
+            Error::Transport(web3::error::TransportError::Message(format!(
+                "none of {ETH_RPC_ENDPOINTS_VAR}, {ETH_IPC_PATH_VAR}, {INFURA_URL_VAR} is set"
+            )))
+        })?;
+    build_single_transport(&first_endpoint).await
+}
+
+/// Builds a transport suitable for `eth_subscribe`. Quorum/fallback groups
+/// have no well-defined way to reconcile a push subscription across
+/// endpoints, so live mode always runs over a single endpoint — the first
+/// entry of `ETH_RPC_ENDPOINTS` when that's all that's configured, or the
+/// `ETH_IPC_PATH`/`INFURA_URL` endpoint otherwise.
+pub async fn build_subscribable_transport() -> web3::Result<EthTransport> {
+    build_configured_transport().await
+}